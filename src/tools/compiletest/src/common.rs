@@ -9,6 +9,7 @@
 // except according to those terms.
 pub use self::Mode::*;
 
+use std::env;
 use std::fmt;
 use std::str::FromStr;
 use std::path::PathBuf;
@@ -97,24 +98,46 @@ impl fmt::Display for Mode {
 
 #[derive(Clone)]
 pub enum CompareMode {
-    Nll
+    Nll,
+    Polonius,
 }
 
 impl CompareMode {
     pub(crate) fn to_str(&self) -> &'static str {
         match *self {
-            CompareMode::Nll => "nll"
+            CompareMode::Nll => "nll",
+            CompareMode::Polonius => "polonius",
         }
     }
 
-    pub fn parse(s: String) -> CompareMode {
+    pub fn parse(s: String) -> Result<CompareMode, String> {
         match s.as_str() {
-            "nll" => CompareMode::Nll,
-            x => panic!("unknown --compare-mode option: {}", x),
+            "nll" => Ok(CompareMode::Nll),
+            "polonius" => Ok(CompareMode::Polonius),
+            x => Err(format!("unknown --compare-mode option: {}", x)),
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::CompareMode;
+
+    #[test]
+    fn parse_accepts_known_modes() {
+        assert_eq!(CompareMode::parse("nll".to_string()).unwrap().to_str(), "nll");
+        assert_eq!(CompareMode::parse("polonius".to_string()).unwrap().to_str(), "polonius");
+    }
+
+    #[test]
+    fn parse_rejects_unknown_modes() {
+        assert_eq!(
+            CompareMode::parse("bogus".to_string()).map(|mode| mode.to_str()),
+            Err("unknown --compare-mode option: bogus".to_string())
+        );
+    }
+}
+
 #[derive(Clone)]
 pub struct Config {
     /// The library paths required for running the compiler
@@ -233,6 +256,22 @@ pub struct Config {
     /// mode describing what file the actual ui output will be compared to
     pub compare_mode: Option<CompareMode>,
 
+    /// If true, when an actual output differs from the expected output, overwrite
+    /// the expected output file with the actual output, instead of failing the test.
+    pub bless: bool,
+
+    /// If true, apply the compiler's machine-applicable suggestions to each UI
+    /// test's source and check the result against a `foo.fixed` file.
+    pub rustfix_coverage: bool,
+
+    /// The Rust edition to compile tests with (passed as `--edition=<N>`).
+    /// A test's own `// edition:2018` header takes precedence over this.
+    pub edition: Option<String>,
+
+    /// If true, `logfile` receives one JSON object per test instead of the
+    /// traditional human-readable "parseable log" lines.
+    pub json_output: bool,
+
     // Configuration for various run-make tests frobbing things like C compilers
     // or querying about various LLVM component information.
     pub cc: String,
@@ -245,6 +284,82 @@ pub struct Config {
     pub nodejs: Option<String>,
 }
 
+impl Default for Config {
+    /// A `Config` with sane defaults for driving compiletest from an
+    /// external crate, where most of the bootstrap-only fields (android,
+    /// valgrind, llvm, ...) simply don't apply.
+    ///
+    /// `rustc_path` is resolved by searching `PATH`, `mode` defaults to
+    /// `Ui`, and everything that only matters to rustc's own test suite
+    /// is left empty/`None`. Callers are expected to at least set
+    /// `src_base` before calling `run_tests`.
+    fn default() -> Config {
+        Config {
+            compile_lib_path: PathBuf::new(),
+            run_lib_path: PathBuf::new(),
+            rustc_path: find_rustc(),
+            rustdoc_path: None,
+            lldb_python: String::new(),
+            docck_python: String::new(),
+            llvm_filecheck: None,
+            valgrind_path: None,
+            force_valgrind: false,
+            src_base: PathBuf::new(),
+            build_base: env::temp_dir().join("compiletest"),
+            stage_id: String::new(),
+            mode: Ui,
+            run_ignored: false,
+            filter: None,
+            filter_exact: false,
+            logfile: None,
+            runtool: None,
+            host_rustcflags: None,
+            target_rustcflags: None,
+            target: String::new(),
+            host: String::new(),
+            gdb: None,
+            gdb_version: None,
+            gdb_native_rust: false,
+            lldb_version: None,
+            llvm_version: None,
+            system_llvm: false,
+            android_cross_path: PathBuf::new(),
+            adb_path: String::new(),
+            adb_test_dir: String::new(),
+            adb_device_status: false,
+            lldb_python_dir: None,
+            verbose: false,
+            quiet: false,
+            color: ColorConfig::AutoColor,
+            remote_test_client: None,
+            compare_mode: None,
+            bless: false,
+            rustfix_coverage: false,
+            edition: None,
+            json_output: false,
+            cc: String::new(),
+            cxx: String::new(),
+            cflags: String::new(),
+            ar: String::new(),
+            linker: None,
+            llvm_components: String::new(),
+            llvm_cxxflags: String::new(),
+            nodejs: None,
+        }
+    }
+}
+
+/// Looks for a `rustc` (or `rustc.exe`) executable on `PATH`, falling back
+/// to the bare command name so the shell can still resolve it.
+fn find_rustc() -> PathBuf {
+    let exe = if cfg!(windows) { "rustc.exe" } else { "rustc" };
+    env::var_os("PATH")
+        .and_then(|paths| {
+            env::split_paths(&paths).map(|dir| dir.join(exe)).find(|candidate| candidate.is_file())
+        })
+        .unwrap_or_else(|| PathBuf::from(exe))
+}
+
 #[derive(Clone)]
 pub struct TestPaths {
     pub file: PathBuf,         // e.g., compile-test/foo/bar/baz.rs
@@ -269,6 +384,9 @@ pub fn expected_output_path(testpaths: &TestPaths,
     testpaths.file.with_extension(extension)
 }
 
-pub const UI_EXTENSIONS: &[&str] = &[UI_STDERR, UI_STDOUT];
+pub const UI_EXTENSIONS: &[&str] = &[UI_STDERR, UI_STDOUT, UI_FIXED];
 pub const UI_STDERR: &str = "stderr";
 pub const UI_STDOUT: &str = "stdout";
+/// The rustfix-applied version of a UI test's source, checked against the
+/// compiler's machine-applicable suggestions.
+pub const UI_FIXED: &str = "fixed";
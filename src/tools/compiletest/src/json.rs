@@ -0,0 +1,116 @@
+// Copyright 2012-2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The structured format written to `Config::logfile`.
+//!
+//! Historically the logfile was just a "parseable log" with no defined
+//! shape beyond being human-readable. When `Config::json_output` is set,
+//! one JSON object per test is appended instead, so CI systems and
+//! dashboards can ingest results without scraping text.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use rustc_serialize::json::Json;
+
+/// The outcome of running a single test, ready to be logged.
+pub struct TestResult {
+    pub name: String,
+    pub mode: String,
+    pub compare_mode: Option<String>,
+    pub passed: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// The `.stdout`/`.stderr` contents `stdout`/`stderr` were compared
+    /// against, when a UI-style mismatch is why the test failed. `None`
+    /// when the test passed, isn't a UI-style mode, or was blessed.
+    pub expected_stdout: Option<String>,
+    pub expected_stderr: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl TestResult {
+    fn to_json(&self) -> Json {
+        let mut object = BTreeMap::new();
+        object.insert("name".to_string(), Json::String(self.name.clone()));
+        object.insert("mode".to_string(), Json::String(self.mode.clone()));
+        object.insert("compare_mode".to_string(), match self.compare_mode {
+            Some(ref mode) => Json::String(mode.clone()),
+            None => Json::Null,
+        });
+        object.insert("result".to_string(),
+                      Json::String(if self.passed { "pass" } else { "fail" }.to_string()));
+        object.insert("stdout".to_string(), Json::String(self.stdout.clone()));
+        object.insert("stderr".to_string(), Json::String(self.stderr.clone()));
+        object.insert("expected_stdout".to_string(), match self.expected_stdout {
+            Some(ref expected) => Json::String(expected.clone()),
+            None => Json::Null,
+        });
+        object.insert("expected_stderr".to_string(), match self.expected_stderr {
+            Some(ref expected) => Json::String(expected.clone()),
+            None => Json::Null,
+        });
+        object.insert("duration_ms".to_string(), Json::U64(self.duration_ms));
+        Json::Object(object)
+    }
+
+    fn to_plain(&self) -> String {
+        format!("{} {} ... {}",
+                self.mode,
+                self.name,
+                if self.passed { "ok" } else { "FAILED" })
+    }
+}
+
+/// Appends one line describing `result` to `logfile`: a JSON object if
+/// `json_output` is set, otherwise the traditional human-readable line.
+pub fn append(logfile: &Path, result: &TestResult, json_output: bool) {
+    let line = if json_output { result.to_json().to_string() } else { result.to_plain() };
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(logfile) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TestResult;
+    use rustc_serialize::json::Json;
+
+    fn sample(passed: bool) -> TestResult {
+        TestResult {
+            name: "foo.rs".to_string(),
+            mode: "ui".to_string(),
+            compare_mode: None,
+            passed,
+            stdout: "stdout text".to_string(),
+            stderr: "stderr text".to_string(),
+            expected_stdout: None,
+            expected_stderr: if passed { None } else { Some("expected stderr".to_string()) },
+            duration_ms: 42,
+        }
+    }
+
+    #[test]
+    fn to_plain_reports_ok_or_failed() {
+        assert_eq!(sample(true).to_plain(), "ui foo.rs ... ok");
+        assert_eq!(sample(false).to_plain(), "ui foo.rs ... FAILED");
+    }
+
+    #[test]
+    fn to_json_includes_the_pass_fail_result_and_expected_output() {
+        let json = sample(false).to_json();
+        assert_eq!(json.find("result").and_then(Json::as_string), Some("fail"));
+        assert_eq!(json.find("stderr").and_then(Json::as_string), Some("stderr text"));
+        assert_eq!(json.find("expected_stderr").and_then(Json::as_string), Some("expected stderr"));
+        assert!(json.find("expected_stdout").map_or(false, Json::is_null));
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright 2012-2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! compiletest, usable as a library.
+//!
+//! Besides driving rustc's own UI/codegen/run-pass test suites, this
+//! crate can be pulled in as a dev-dependency by any crate that wants to
+//! assert on the compiler errors its macros or trait bounds produce:
+//! build a `common::Config` with `Config::default()`, set at least
+//! `src_base` (and usually `mode` and `target_rustcflags`), and hand it
+//! to `run_tests`.
+//!
+//! This crate depends on the unstable `test` crate, so it only builds
+//! with a nightly toolchain (or `RUSTC_BOOTSTRAP=1` set).
+
+#![feature(test)]
+
+extern crate rustc_serialize;
+extern crate test;
+
+pub mod common;
+mod json;
+mod rustfix;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::time::Instant;
+
+use common::{CompareMode, Config, TestPaths, UI_FIXED, UI_STDERR, UI_STDOUT, expected_output_path};
+use json::TestResult;
+
+/// Walks `config.src_base` for test sources and runs them against
+/// `config.rustc_path`, reporting how many failed.
+///
+/// Returns the number of tests that failed.
+pub fn run_tests(config: &Config) -> usize {
+    let mut failed = 0;
+    for testpaths in collect_tests(&config.src_base, &config.src_base) {
+        let result = run_test(config, &testpaths);
+        if let Some(ref logfile) = config.logfile {
+            json::append(logfile, &result, config.json_output);
+        }
+        if !result.passed {
+            failed += 1;
+        }
+    }
+    failed
+}
+
+fn collect_tests(base: &Path, dir: &Path) -> Vec<TestPaths> {
+    let mut tests = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return tests,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            if path.file_name().map_or(false, |name| name == "auxiliary") {
+                continue;
+            }
+            tests.extend(collect_tests(base, &path));
+        } else if path.extension().map_or(false, |ext| ext == "rs") {
+            let relative_dir = path.parent()
+                .and_then(|p| p.strip_prefix(base).ok())
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default();
+            tests.push(TestPaths {
+                file: path,
+                base: base.to_path_buf(),
+                relative_dir,
+            });
+        }
+    }
+    tests
+}
+
+/// The extra rustc flags that select the analysis backend a `CompareMode`
+/// checks a test against.
+fn compare_mode_args(compare_mode: &Option<CompareMode>) -> Vec<&'static str> {
+    match *compare_mode {
+        Some(CompareMode::Nll) => vec!["-Z", "borrowck=mir"],
+        Some(CompareMode::Polonius) => vec!["-Z", "polonius"],
+        None => vec![],
+    }
+}
+
+/// Looks for a `// edition:2018`-style header among a test's leading
+/// comment lines.
+fn parse_edition_header(source: &str) -> Option<String> {
+    source.lines()
+        .take_while(|line| line.starts_with("//") || line.trim().is_empty())
+        .find_map(|line| {
+            let line = line.trim_start_matches('/').trim();
+            if line.starts_with("edition:") {
+                Some(line["edition:".len()..].trim().to_string())
+            } else {
+                None
+            }
+        })
+}
+
+fn run_test(config: &Config, testpaths: &TestPaths) -> TestResult {
+    let started = Instant::now();
+    let name = testpaths.file.display().to_string();
+    let compare_mode = config.compare_mode.as_ref().map(|mode| mode.to_str().to_string());
+
+    let source = fs::read_to_string(&testpaths.file).unwrap_or_default();
+    let edition = parse_edition_header(&source).or_else(|| config.edition.clone());
+
+    let mut rustc = Command::new(&config.rustc_path);
+    rustc.arg(&testpaths.file);
+    rustc.args(&compare_mode_args(&config.compare_mode));
+    if let Some(ref edition) = edition {
+        rustc.arg(format!("--edition={}", edition));
+    }
+    if let Some(ref flags) = config.target_rustcflags {
+        rustc.args(flags.split_whitespace());
+    }
+    let output = match rustc.output() {
+        Ok(output) => output,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+            return failed_result(name, config.mode, compare_mode, started);
+        }
+        Err(_) => return failed_result(name, config.mode, compare_mode, started),
+    };
+    let revision = edition.as_ref().map(String::as_str);
+    let status_ok = match config.mode {
+        common::CompileFail => !output.status.success(),
+        _ => output.status.success(),
+    };
+    let (stderr_ok, expected_stderr) = compare_output(config, testpaths, revision, UI_STDERR, &output.stderr);
+    let (stdout_ok, expected_stdout) = compare_output(config, testpaths, revision, UI_STDOUT, &output.stdout);
+    let fixed_ok = !config.rustfix_coverage ||
+        check_rustfix(config, testpaths, &source, revision);
+    TestResult {
+        name,
+        mode: config.mode.to_string(),
+        compare_mode,
+        passed: status_ok && stderr_ok && stdout_ok && fixed_ok,
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        expected_stdout: expected_stdout.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+        expected_stderr: expected_stderr.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+        duration_ms: duration_ms(started),
+    }
+}
+
+fn failed_result(
+    name: String,
+    mode: common::Mode,
+    compare_mode: Option<String>,
+    started: Instant,
+) -> TestResult {
+    TestResult {
+        name,
+        mode: mode.to_string(),
+        compare_mode,
+        passed: false,
+        stdout: String::new(),
+        stderr: String::new(),
+        expected_stdout: None,
+        expected_stderr: None,
+        duration_ms: duration_ms(started),
+    }
+}
+
+fn duration_ms(started: Instant) -> u64 {
+    let elapsed = started.elapsed();
+    elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis())
+}
+
+/// Re-runs the test with `--error-format=json`, applies every
+/// machine-applicable suggestion rustc reports, and compares the result
+/// against `foo.fixed`.
+fn check_rustfix(config: &Config, testpaths: &TestPaths, source: &str, revision: Option<&str>) -> bool {
+    let mut rustc = Command::new(&config.rustc_path);
+    rustc.arg(&testpaths.file).arg("--error-format=json");
+    rustc.args(&compare_mode_args(&config.compare_mode));
+    if let Some(edition) = revision {
+        rustc.arg(format!("--edition={}", edition));
+    }
+    if let Some(ref flags) = config.target_rustcflags {
+        rustc.args(flags.split_whitespace());
+    }
+    let output = match rustc.output() {
+        Ok(output) => output,
+        Err(_) => return false,
+    };
+    let diagnostics = String::from_utf8_lossy(&output.stderr);
+    let fixed = rustfix::apply_suggestions(source, &diagnostics);
+    compare_output(config, testpaths, revision, UI_FIXED, fixed.as_bytes()).0
+}
+
+/// Compares `actual` against the file returned by `expected_output_path`.
+///
+/// Only wired up for UI-style tests (`Ui`, `CompileFail`): other modes
+/// don't maintain `.stdout`/`.stderr` files, so a `RunPass` test that
+/// legitimately prints to stdout shouldn't start failing because of it.
+///
+/// When `config.bless` is set, a mismatch (or a missing expected file) isn't
+/// reported as a failure: the expected file is overwritten with `actual`
+/// instead, or removed entirely if `actual` is empty.
+///
+/// Returns whether `actual` was accepted, and, on a non-blessed mismatch,
+/// the expected contents that were compared against (so callers can pair
+/// up expected/actual for reporting).
+fn compare_output(
+    config: &Config,
+    testpaths: &TestPaths,
+    revision: Option<&str>,
+    kind: &str,
+    actual: &[u8],
+) -> (bool, Option<Vec<u8>>) {
+    if config.mode != common::Ui && config.mode != common::CompileFail {
+        return (true, None);
+    }
+    let expected_path = expected_output_path(testpaths, revision, &config.compare_mode, kind);
+    let expected = fs::read(&expected_path).unwrap_or_default();
+    if expected == actual {
+        return (true, None);
+    }
+    if !config.bless {
+        return (false, Some(expected));
+    }
+    if actual.is_empty() {
+        let _ = fs::remove_file(&expected_path);
+    } else {
+        let _ = fs::write(&expected_path, actual);
+    }
+    (true, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_output, parse_edition_header};
+    use common::{self, Config, TestPaths};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    #[test]
+    fn parse_edition_header_reads_a_leading_comment() {
+        let source = "// edition:2018\n// some other note\nfn main() {}\n";
+        assert_eq!(parse_edition_header(source), Some("2018".to_string()));
+    }
+
+    #[test]
+    fn parse_edition_header_stops_at_the_first_non_comment_line() {
+        let source = "fn main() {}\n// edition:2018\n";
+        assert_eq!(parse_edition_header(source), None);
+    }
+
+    #[test]
+    fn parse_edition_header_absent_returns_none() {
+        assert_eq!(parse_edition_header("fn main() {}\n"), None);
+    }
+
+    // A scratch directory under the OS temp dir, unique per test so
+    // concurrent `cargo test` runs don't trample each other's fixtures.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("compiletest-test-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn testpaths_for(file: PathBuf) -> TestPaths {
+        TestPaths { base: file.parent().unwrap().to_path_buf(), relative_dir: PathBuf::new(), file }
+    }
+
+    #[test]
+    fn compare_output_bless_overwrites_a_mismatched_expected_file() {
+        let dir = scratch_dir("bless-overwrite");
+        let testpaths = testpaths_for(dir.join("foo.rs"));
+        fs::write(dir.join("foo.stderr"), b"old output").unwrap();
+
+        let mut config = Config::default();
+        config.mode = common::Ui;
+        config.bless = true;
+        let (ok, expected) = compare_output(&config, &testpaths, None, common::UI_STDERR, b"new output");
+
+        assert!(ok);
+        assert!(expected.is_none());
+        assert_eq!(fs::read(dir.join("foo.stderr")).unwrap(), b"new output");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compare_output_bless_removes_the_expected_file_when_actual_is_empty() {
+        let dir = scratch_dir("bless-remove");
+        let testpaths = testpaths_for(dir.join("foo.rs"));
+        fs::write(dir.join("foo.stderr"), b"old output").unwrap();
+
+        let mut config = Config::default();
+        config.mode = common::Ui;
+        config.bless = true;
+        let (ok, expected) = compare_output(&config, &testpaths, None, common::UI_STDERR, b"");
+
+        assert!(ok);
+        assert!(expected.is_none());
+        assert!(!dir.join("foo.stderr").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2012-2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Applies rustc's machine-applicable suggestions to a UI test's source,
+//! so that `foo.fixed` can be checked against what the compiler actually
+//! recommends rather than just what it says in prose.
+
+use rustc_serialize::json::Json;
+
+/// One machine-applicable edit extracted from a `--error-format=json` diagnostic.
+struct Replacement {
+    byte_start: usize,
+    byte_end: usize,
+    replacement: String,
+}
+
+fn collect_replacements(diagnostics: &str) -> Vec<Replacement> {
+    let mut replacements = Vec::new();
+    for line in diagnostics.lines() {
+        let diagnostic = match Json::from_str(line) {
+            Ok(diagnostic) => diagnostic,
+            Err(_) => continue,
+        };
+        let children = match diagnostic.find("children").and_then(Json::as_array) {
+            Some(children) => children,
+            None => continue,
+        };
+        for child in children {
+            let spans = match child.find("spans").and_then(Json::as_array) {
+                Some(spans) => spans,
+                None => continue,
+            };
+            for span in spans {
+                let is_machine_applicable =
+                    span.find("suggestion_applicability").and_then(Json::as_string) ==
+                        Some("MachineApplicable");
+                if !is_machine_applicable {
+                    continue;
+                }
+                let replacement = match span.find("suggested_replacement").and_then(Json::as_string) {
+                    Some(replacement) => replacement.to_string(),
+                    None => continue,
+                };
+                let byte_start = span.find("byte_start").and_then(Json::as_u64);
+                let byte_end = span.find("byte_end").and_then(Json::as_u64);
+                if let (Some(byte_start), Some(byte_end)) = (byte_start, byte_end) {
+                    replacements.push(Replacement {
+                        byte_start: byte_start as usize,
+                        byte_end: byte_end as usize,
+                        replacement,
+                    });
+                }
+            }
+        }
+    }
+    replacements
+}
+
+/// Applies every machine-applicable suggestion found in `diagnostics`
+/// (rustc's line-delimited `--error-format=json` output) to `source`.
+///
+/// Edits are applied in reverse byte-offset order so that earlier edits
+/// don't shift the spans of edits still to come, and a span that
+/// overlaps one already applied is skipped rather than corrupting the
+/// source.
+pub fn apply_suggestions(source: &str, diagnostics: &str) -> String {
+    let mut replacements = collect_replacements(diagnostics);
+    replacements.sort_by(|a, b| b.byte_start.cmp(&a.byte_start));
+
+    let mut fixed = source.to_string();
+    let mut applied_from = usize::max_value();
+    for replacement in replacements {
+        if replacement.byte_end > applied_from {
+            continue;
+        }
+        fixed.replace_range(replacement.byte_start..replacement.byte_end, &replacement.replacement);
+        applied_from = replacement.byte_start;
+    }
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply_suggestions;
+
+    // A trimmed-down `--error-format=json` diagnostic with one
+    // machine-applicable suggestion, of the shape rustc actually emits.
+    fn diagnostic(byte_start: usize, byte_end: usize, replacement: &str) -> String {
+        format!(
+            r#"{{"message":"unused import","children":[{{"message":"remove the import","spans":[{{"byte_start":{},"byte_end":{},"suggestion_applicability":"MachineApplicable","suggested_replacement":"{}"}}]}}]}}"#,
+            byte_start, byte_end, replacement
+        )
+    }
+
+    #[test]
+    fn applies_a_single_suggestion() {
+        let source = "use std::foo;\nfn main() {}\n";
+        let diagnostics = diagnostic(4, 12, "std::bar");
+        assert_eq!(apply_suggestions(source, &diagnostics), "use std::bar;\nfn main() {}\n");
+    }
+
+    #[test]
+    fn applies_multiple_non_overlapping_suggestions_in_any_order() {
+        let source = "aaa bbb";
+        let diagnostics = format!("{}\n{}", diagnostic(0, 3, "xxx"), diagnostic(4, 7, "yyy"));
+        assert_eq!(apply_suggestions(source, &diagnostics), "xxx yyy");
+    }
+
+    #[test]
+    fn skips_suggestions_that_overlap_one_already_applied() {
+        let source = "aaa bbb";
+        // The second suggestion's span (0..5) overlaps the first (0..3).
+        // Both start at 0, so the stable sort keeps input order and 0..3
+        // is applied first; 0..5 must be dropped.
+        let diagnostics = format!("{}\n{}", diagnostic(0, 3, "xxx"), diagnostic(0, 5, "zzzzz"));
+        assert_eq!(apply_suggestions(source, &diagnostics), "xxx bbb");
+    }
+
+    #[test]
+    fn ignores_suggestions_that_are_not_machine_applicable() {
+        let source = "aaa bbb";
+        let diagnostics = r#"{"message":"m","children":[{"message":"c","spans":[{"byte_start":0,"byte_end":3,"suggestion_applicability":"MaybeIncorrect","suggested_replacement":"xxx"}]}]}"#;
+        assert_eq!(apply_suggestions(source, diagnostics), source);
+    }
+
+    #[test]
+    fn ignores_malformed_json_lines() {
+        let source = "aaa bbb";
+        assert_eq!(apply_suggestions(source, "not json"), source);
+    }
+}